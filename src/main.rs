@@ -6,55 +6,356 @@ use std::{
 use eframe::{App, CreationContext};
 use egui::{Id, Ui};
 use egui_snarl::{
-    ui::{AnyPins, PinInfo, SnarlStyle, SnarlViewer},
+    ui::{AnyPins, PinInfo, SnarlStyle, SnarlViewer, WireStyle},
     InPin, InPinId, NodeId, OutPin, OutPinId, Snarl,
 };
 use layout::{
-    core::format::Visible,
+    core::{base::Orientation, format::Visible, geometry::Point, style::StyleAttr},
     gv::{
-        parser::ast::{EdgeStmt, Graph, NodeStmt, Stmt},
+        parser::ast::{EdgeStmt, Graph, NodeStmt, Stmt, StmtList, SubGraph},
         DotParser, GraphBuilder,
     },
-    std_shapes::shapes::{Element, ShapeKind},
-    topo::{layout::VisualGraph, placer::place::Placer},
+    std_shapes::shapes::{Arrow, Element, ShapeKind},
+    topo::{
+        layout::{NodeHandle, VisualGraph},
+        placer::place::Placer,
+    },
 };
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Error parsing DOT graph: [{0}]")]
     DotParserError(String),
+    #[error("DOT edge references unknown node: [{0}]")]
+    UnknownNode(String),
 }
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// Attributes parsed off a DOT node/edge statement (`color`, `style`, `shape`, ...)
+/// that don't have a dedicated field elsewhere. Keyed by the raw DOT attribute name.
+type AttrMap = BTreeMap<String, String>;
+
+/// An `EdgeStmt`'s own `[color=..., style=...]` attribute list, kept separate from
+/// either endpoint's `Node::attrs` and looked up by the pin pair it connects, since
+/// DOT lets an edge style its own wire independently of both nodes it touches.
+type EdgeStyles = Vec<(OutPinId, InPinId, AttrMap)>;
+
+/// Look up the DOT attrs an `EdgeStmt` attached directly to the wire between
+/// `from` and `to`, if any.
+fn edge_attrs_for(edge_styles: &EdgeStyles, from: OutPinId, to: InPinId) -> Option<&AttrMap> {
+    edge_styles
+        .iter()
+        .find(|(f, t, _)| *f == from && *t == to)
+        .map(|(_, _, attrs)| attrs)
+}
+
+/// Build a `WireStyle` from an edge's own `color`/`style` attrs, falling back to
+/// `fallback` (the endpoint node's style) when the edge carries neither.
+fn wire_style_from_attrs(attrs: &AttrMap, fallback: WireStyle) -> WireStyle {
+    if attrs.get("color").is_none() && attrs.get("style").is_none() {
+        return fallback;
+    }
+    let color = color_from_attr(attrs.get("color").map(String::as_str));
+    match attrs.get("style").map(String::as_str) {
+        Some("dashed") | Some("dotted") => WireStyle::new(2.0, color).dashed(true),
+        _ => WireStyle::new(2.0, color),
+    }
+}
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 struct Node {
     name: String,
+    attrs: AttrMap,
 }
 
 impl Node {
     fn new(name: &str) -> Self {
-        Self { name: name.to_string() }
+        Self::with_attrs(name, AttrMap::new())
+    }
+
+    fn with_attrs(name: &str, attrs: AttrMap) -> Self {
+        Self {
+            name: name.to_string(),
+            attrs,
+        }
     }
+
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn color(&self) -> egui::Color32 {
+        color_from_attr(self.attrs.get("color").map(String::as_str))
+    }
+
+    fn pin_info(&self) -> PinInfo {
+        match self.attrs.get("shape").map(String::as_str) {
+            Some("doublecircle") => PinInfo::star().with_fill(self.color()),
+            _ => PinInfo::circle().with_fill(self.color()),
+        }
+    }
+
+    fn wire_style(&self) -> WireStyle {
+        match self.attrs.get("style").map(String::as_str) {
+            Some("dashed") | Some("dotted") => WireStyle::new(2.0, self.color()).dashed(true),
+            _ => WireStyle::new(2.0, self.color()),
+        }
+    }
+}
+
+/// Map a DOT `color` attribute (named color or `#rrggbb` hex) onto an egui color,
+/// falling back to the viewer's default gray when the attribute is absent or unknown.
+fn color_from_attr(color: Option<&str>) -> egui::Color32 {
+    match color {
+        Some("red") => egui::Color32::RED,
+        Some("green") => egui::Color32::GREEN,
+        Some("blue") => egui::Color32::BLUE,
+        Some("yellow") => egui::Color32::YELLOW,
+        Some("orange") => egui::Color32::from_rgb(255, 165, 0),
+        Some(hex) if hex.starts_with('#') => {
+            egui::Color32::from_hex(hex).unwrap_or(egui::Color32::GRAY)
+        }
+        _ => egui::Color32::GRAY,
+    }
+}
+
+/// A reversible edit to a `Snarl<Node>`. Every mutation the viewer makes goes
+/// through `CommandHistory` as one of these instead of hitting the snarl directly,
+/// so a misclick can be undone.
+enum Command {
+    InsertNode {
+        id: NodeId,
+        pos: egui::Pos2,
+        node: Node,
+    },
+    RemoveNode {
+        id: NodeId,
+        pos: egui::Pos2,
+        node: Node,
+        wires: Vec<(OutPinId, InPinId)>,
+    },
+    Connect {
+        from: OutPinId,
+        to: InPinId,
+    },
+    Disconnect {
+        from: OutPinId,
+        to: InPinId,
+    },
+    MoveNode {
+        id: NodeId,
+        old_pos: egui::Pos2,
+        new_pos: egui::Pos2,
+    },
+}
+
+impl Command {
+    /// Apply the command to `snarl`: the first run of every command except
+    /// `InsertNode` (see `CommandHistory::insert_node`), and every redo. An
+    /// `InsertNode` only reaches here on redo, when it already has an `id` to
+    /// restore onto, so insertion goes through `insert_node_at` instead of
+    /// letting `Snarl` allocate a fresh one.
+    fn apply(&self, snarl: &mut Snarl<Node>) {
+        match self {
+            Command::InsertNode { id, pos, node } => {
+                snarl.insert_node_at(*id, *pos, node.clone());
+            }
+            Command::RemoveNode { id, .. } => {
+                snarl.remove_node(*id);
+            }
+            Command::Connect { from, to } => {
+                snarl.connect(*from, *to);
+            }
+            Command::Disconnect { from, to } => {
+                snarl.disconnect(*from, *to);
+            }
+            Command::MoveNode { id, new_pos, .. } => {
+                if let Some(info) = snarl.get_node_info_mut(*id) {
+                    info.pos = *new_pos;
+                }
+            }
+        }
+    }
+
+    fn undo(&self, snarl: &mut Snarl<Node>) {
+        match self {
+            Command::InsertNode { id, .. } => {
+                snarl.remove_node(*id);
+            }
+            // Restore the node and every wire it had, at its original NodeId,
+            // so anything still referencing that id (selections, other wires) stays valid.
+            Command::RemoveNode {
+                id, pos, node, wires,
+            } => {
+                snarl.insert_node_at(*id, *pos, node.clone());
+                for (from, to) in wires {
+                    snarl.connect(*from, *to);
+                }
+            }
+            Command::Connect { from, to } => {
+                snarl.disconnect(*from, *to);
+            }
+            Command::Disconnect { from, to } => {
+                snarl.connect(*from, *to);
+            }
+            Command::MoveNode { id, old_pos, .. } => {
+                if let Some(info) = snarl.get_node_info_mut(*id) {
+                    info.pos = *old_pos;
+                }
+            }
+        }
+    }
+}
+
+/// Undo/redo stacks for `Command`s applied to a `Snarl<Node>`. Doing a new command
+/// after undoing clears the redo stack, same as a typical editor.
+#[derive(Default)]
+struct CommandHistory {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+}
+
+impl CommandHistory {
+    fn record(&mut self, cmd: Command) {
+        self.undo_stack.push(cmd);
+        self.redo_stack.clear();
+    }
+
+    /// Let `Snarl` allocate the `NodeId` as it always does; the id is only
+    /// known after insertion, so the command is recorded afterwards rather
+    /// than applied through `Command::apply`.
+    fn insert_node(&mut self, snarl: &mut Snarl<Node>, pos: egui::Pos2, node: Node) -> NodeId {
+        let id = snarl.insert_node(pos, node.clone());
+        self.record(Command::InsertNode { id, pos, node });
+        id
+    }
+
+    fn remove_node(&mut self, snarl: &mut Snarl<Node>, id: NodeId) {
+        let Some(node) = snarl.get_node(id).cloned() else {
+            return;
+        };
+        let pos = snarl
+            .get_node_info(id)
+            .map(|info| info.pos)
+            .unwrap_or_default();
+        let wires: Vec<_> = snarl
+            .wires()
+            .filter(|(from, to)| from.node == id || to.node == id)
+            .collect();
+        let cmd = Command::RemoveNode {
+            id,
+            pos,
+            node,
+            wires,
+        };
+        cmd.apply(snarl);
+        self.record(cmd);
+    }
+
+    fn connect(&mut self, snarl: &mut Snarl<Node>, from: OutPinId, to: InPinId) {
+        let cmd = Command::Connect { from, to };
+        cmd.apply(snarl);
+        self.record(cmd);
+    }
+
+    fn disconnect(&mut self, snarl: &mut Snarl<Node>, from: OutPinId, to: InPinId) {
+        let cmd = Command::Disconnect { from, to };
+        cmd.apply(snarl);
+        self.record(cmd);
+    }
+
+    /// Record a move that the canvas already applied (e.g. a drag), without re-applying it.
+    fn record_move(&mut self, id: NodeId, old_pos: egui::Pos2, new_pos: egui::Pos2) {
+        self.record(Command::MoveNode { id, old_pos, new_pos });
+    }
+
+    fn undo(&mut self, snarl: &mut Snarl<Node>) {
+        if let Some(cmd) = self.undo_stack.pop() {
+            cmd.undo(snarl);
+            self.redo_stack.push(cmd);
+        }
+    }
+
+    fn redo(&mut self, snarl: &mut Snarl<Node>) {
+        if let Some(cmd) = self.redo_stack.pop() {
+            cmd.apply(snarl);
+            self.undo_stack.push(cmd);
+        }
+    }
 }
 
-struct DemoViewer;
+/// The node kinds the node-finder offers, seeded from the shapes `node_name`/the
+/// DOT layout code already understands.
+const NODE_KINDS: &[(&str, &str)] = &[
+    ("Box", "box"),
+    ("Circle", "circle"),
+    ("DoubleCircle", "doublecircle"),
+];
 
-impl SnarlViewer<Node> for DemoViewer {
+/// Case-insensitive subsequence match: every char of `query` must appear in
+/// `candidate` in order, though not necessarily contiguously.
+fn fuzzy_match(query: &str, candidate: &str) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    let mut candidate = candidate.chars().map(|c| c.to_ascii_lowercase());
+    'query: for q in query.chars().map(|c| c.to_ascii_lowercase()) {
+        for c in candidate.by_ref() {
+            if c == q {
+                continue 'query;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+static FINDER_NODE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Node-finder picks only name a *kind* (`"Circle"`, ...), which isn't a unique
+/// identifier, so give each one created this way its own DOT-safe name.
+fn unique_finder_name(kind: &str) -> String {
+    format!("{kind}{}", FINDER_NODE_COUNTER.fetch_add(1, Ordering::SeqCst))
+}
+
+/// Text-filter field plus the matching node kinds, shown by both the graph menu
+/// and the dropped-wire menu. Returns the chosen kind, if the user picked one.
+fn node_finder(ui: &mut Ui, query: &mut String) -> Option<(&'static str, &'static str)> {
+    ui.label("Find node");
+    ui.text_edit_singleline(query);
+
+    let mut chosen = None;
+    for &(name, shape) in NODE_KINDS {
+        if fuzzy_match(query, name) && ui.button(name).clicked() {
+            chosen = Some((name, shape));
+        }
+    }
+    chosen
+}
+
+struct DemoViewer<'a> {
+    history: &'a mut CommandHistory,
+    finder_query: &'a mut String,
+    edge_styles: &'a EdgeStyles,
+}
+
+impl SnarlViewer<Node> for DemoViewer<'_> {
     #[inline]
     fn connect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<Node>) {
         for &remote in &to.remotes {
-            snarl.disconnect(remote, to.id);
+            self.history.disconnect(snarl, remote, to.id);
         }
 
-        snarl.connect(from.id, to.id);
+        self.history.connect(snarl, from.id, to.id);
     }
 
     fn title(&mut self, node: &Node) -> String {
-        node.name.to_string()
+        match node.attrs.get("cluster") {
+            // Group cluster_* subgraph members under a shared title prefix.
+            Some(cluster) => format!("[{cluster}] {}", node.name),
+            None => node.name.to_string(),
+        }
     }
 
     fn inputs(&mut self, node: &Node) -> usize {
@@ -67,22 +368,36 @@ impl SnarlViewer<Node> for DemoViewer {
 
     fn show_input(
         &mut self,
-        _pin: &InPin,
+        pin: &InPin,
         _ui: &mut Ui,
         _scale: f32,
-        _snarl: &mut Snarl<Node>,
+        snarl: &mut Snarl<Node>,
     ) -> PinInfo {
-        PinInfo::default()
+        let node = &snarl[pin.id.node];
+        let style = pin
+            .remotes
+            .first()
+            .and_then(|&from| edge_attrs_for(self.edge_styles, from, pin.id))
+            .map(|attrs| wire_style_from_attrs(attrs, node.wire_style()))
+            .unwrap_or_else(|| node.wire_style());
+        node.pin_info().with_wire_style(style)
     }
 
     fn show_output(
         &mut self,
-        _pin: &OutPin,
+        pin: &OutPin,
         _ui: &mut Ui,
         _scale: f32,
-        _snarl: &mut Snarl<Node>,
+        snarl: &mut Snarl<Node>,
     ) -> PinInfo {
-        PinInfo::default()
+        let node = &snarl[pin.id.node];
+        let style = pin
+            .remotes
+            .first()
+            .and_then(|&to| edge_attrs_for(self.edge_styles, pin.id, to))
+            .map(|attrs| wire_style_from_attrs(attrs, node.wire_style()))
+            .unwrap_or_else(|| node.wire_style());
+        node.pin_info().with_wire_style(style)
     }
 
     fn has_graph_menu(&mut self, _pos: egui::Pos2, _snarl: &mut Snarl<Node>) -> bool {
@@ -96,15 +411,67 @@ impl SnarlViewer<Node> for DemoViewer {
         _scale: f32,
         snarl: &mut Snarl<Node>,
     ) {
-        ui.label("Add node");
-        if ui.button("String").clicked() {
-            snarl.insert_node(pos, Node::new(""));
+        if let Some((kind, shape)) = node_finder(ui, self.finder_query) {
+            let mut attrs = AttrMap::new();
+            attrs.insert("shape".to_string(), shape.to_string());
+            let name = unique_finder_name(kind);
+            self.history
+                .insert_node(snarl, pos, Node::with_attrs(&name, attrs));
+            self.finder_query.clear();
             ui.close_menu();
         }
     }
 
     fn has_dropped_wire_menu(&mut self, _src_pins: AnyPins, _snarl: &mut Snarl<Node>) -> bool {
-        false
+        true
+    }
+
+    fn show_dropped_wire_menu(
+        &mut self,
+        pos: egui::Pos2,
+        ui: &mut Ui,
+        _scale: f32,
+        src_pins: AnyPins,
+        snarl: &mut Snarl<Node>,
+    ) {
+        if let Some((kind, shape)) = node_finder(ui, self.finder_query) {
+            let mut attrs = AttrMap::new();
+            attrs.insert("shape".to_string(), shape.to_string());
+            let name = unique_finder_name(kind);
+            let new_id = self
+                .history
+                .insert_node(snarl, pos, Node::with_attrs(&name, attrs));
+
+            match src_pins {
+                AnyPins::Out(pins) => {
+                    for &from in pins {
+                        self.history.connect(
+                            snarl,
+                            from,
+                            InPinId {
+                                node: new_id,
+                                input: 0,
+                            },
+                        );
+                    }
+                }
+                AnyPins::In(pins) => {
+                    for &to in pins {
+                        self.history.connect(
+                            snarl,
+                            OutPinId {
+                                node: new_id,
+                                output: 0,
+                            },
+                            to,
+                        );
+                    }
+                }
+            }
+
+            self.finder_query.clear();
+            ui.close_menu();
+        }
     }
 
     fn has_node_menu(&mut self, _node: &Node) -> bool {
@@ -122,7 +489,7 @@ impl SnarlViewer<Node> for DemoViewer {
     ) {
         ui.label("Node menu");
         if ui.button("Remove").clicked() {
-            snarl.remove_node(node);
+            self.history.remove_node(snarl, node);
             ui.close_menu();
         }
     }
@@ -181,6 +548,12 @@ pub struct DemoApp {
     style: SnarlStyle,
     snarl_ui_id: Option<Id>,
     state: State,
+    history: CommandHistory,
+    finder_query: String,
+    edge_styles: EdgeStyles,
+    /// Positions captured the frame a drag starts, so the whole gesture (however
+    /// many frames it spans) lands one `MoveNode` command, not one per frame.
+    drag_start_positions: Option<BTreeMap<NodeId, egui::Pos2>>,
 }
 
 impl DemoApp {
@@ -212,6 +585,10 @@ impl DemoApp {
             style,
             snarl_ui_id: None,
             state: Default::default(),
+            history: Default::default(),
+            finder_query: String::new(),
+            edge_styles: EdgeStyles::new(),
+            drag_start_positions: None,
         }
     }
 }
@@ -236,6 +613,23 @@ impl App for DemoApp {
 
                 if ui.button("Clear All").clicked() {
                     self.snarl = Default::default();
+                    // The undo/redo stacks hold Commands referencing ids/pins
+                    // from the snarl we just threw away; replaying them against
+                    // an empty snarl would panic, so they have to go too.
+                    self.history = Default::default();
+                }
+
+                ui.add_space(16.0);
+
+                // Undo/Redo move nodes while the pointer is down for the click;
+                // drop the drag snapshot so those moves aren't recorded as a drag.
+                if ui.button("Undo").clicked() {
+                    self.history.undo(&mut self.snarl);
+                    self.drag_start_positions = None;
+                }
+                if ui.button("Redo").clicked() {
+                    self.history.redo(&mut self.snarl);
+                    self.drag_start_positions = None;
                 }
             });
         });
@@ -243,7 +637,10 @@ impl App for DemoApp {
         egui::SidePanel::left("style").show(ctx, |ui| {
             if LOOP_NUM.load(Ordering::SeqCst) == 0 {
                 self.snarl = Default::default();
-                let _ = parse_dot(&mut self.snarl, &self.state.graph_str);
+                self.history = Default::default();
+                if let Ok(styles) = parse_dot(&mut self.snarl, &self.state.graph_str) {
+                    self.edge_styles = styles;
+                }
                 LOOP_NUM.fetch_add(1, Ordering::SeqCst);
             } else {
                 LOOP_NUM.fetch_add(1, Ordering::SeqCst);
@@ -252,7 +649,15 @@ impl App for DemoApp {
                 &mut self.state.graph_str,
             ));
             if ui.add(egui::Button::new("Parse graph")).clicked() {
-                let _ = parse_dot(&mut self.snarl, &self.state.graph_str);
+                if let Ok(styles) = parse_dot(&mut self.snarl, &self.state.graph_str) {
+                    self.edge_styles = styles;
+                }
+            }
+            if ui.add(egui::Button::new("Generate DOT")).clicked() {
+                self.state.graph_str = snarl_to_dot(&self.snarl, &self.edge_styles);
+            }
+            if ui.add(egui::Button::new("Re-layout")).clicked() {
+                let _ = relayout(&mut self.snarl);
             }
             egui::ScrollArea::vertical().show(ui, |ui| {
                 egui_probe::Probe::new(&mut self.style).show(ui);
@@ -287,7 +692,7 @@ impl App for DemoApp {
                     }
 
                     if let Some(id) = remove {
-                        self.snarl.remove_node(id);
+                        self.history.remove_node(&mut self.snarl, id);
                     }
                 });
             });
@@ -296,7 +701,49 @@ impl App for DemoApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             self.snarl_ui_id = Some(ui.id());
 
-            self.snarl.show(&mut DemoViewer, &self.style, "snarl", ui);
+            // A drag (which the canvas applies directly, with no viewer hook) spans
+            // many frames with the pointer held down. Snapshot positions once, the
+            // frame the drag starts, and only diff/record on release, so the whole
+            // gesture lands one `MoveNode` command instead of one per frame.
+            let pointer_down = ui.ctx().input(|i| i.pointer.any_down());
+            if pointer_down && self.drag_start_positions.is_none() {
+                self.drag_start_positions = Some(
+                    self.snarl
+                        .node_ids()
+                        .map(|(id, _)| {
+                            let pos = self
+                                .snarl
+                                .get_node_info(id)
+                                .map(|info| info.pos)
+                                .unwrap_or_default();
+                            (id, pos)
+                        })
+                        .collect(),
+                );
+            }
+
+            self.snarl.show(
+                &mut DemoViewer {
+                    history: &mut self.history,
+                    finder_query: &mut self.finder_query,
+                    edge_styles: &self.edge_styles,
+                },
+                &self.style,
+                "snarl",
+                ui,
+            );
+
+            if !pointer_down {
+                if let Some(positions_before) = self.drag_start_positions.take() {
+                    for (id, old_pos) in positions_before {
+                        if let Some(new_pos) = self.snarl.get_node_info(id).map(|info| info.pos) {
+                            if new_pos != old_pos {
+                                self.history.record_move(id, old_pos, new_pos);
+                            }
+                        }
+                    }
+                }
+            }
         });
     }
 
@@ -363,6 +810,149 @@ fn node_name(e: &Element) -> Result<String> {
     .to_string())
 }
 
+/// Serialize the live snarl graph back to DOT text, so edits made in the
+/// canvas (new nodes, removed nodes, drawn wires) can round-trip through
+/// `parse_dot` instead of only surviving in the eframe storage blob. Carries
+/// `Node::attrs` (`color`/`shape`) and `edge_styles` (`color`/`style`) back out
+/// too, so a parse/generate cycle doesn't flatten a styled graph to plain text.
+fn snarl_to_dot(snarl: &Snarl<Node>, edge_styles: &EdgeStyles) -> String {
+    let mut out = String::from("digraph G {\n");
+
+    // Group nodes by their "cluster" attr so clustered DOT input keeps its
+    // `subgraph cluster_*` grouping across a parse/generate round trip, instead
+    // of flattening every node into the top-level digraph body.
+    let mut clusters: BTreeMap<String, Vec<(NodeId, &Node)>> = BTreeMap::new();
+    let mut unclustered = Vec::new();
+    for (id, node) in snarl.node_ids() {
+        match node.attrs.get("cluster") {
+            Some(cluster) => clusters.entry(cluster.clone()).or_default().push((id, node)),
+            None => unclustered.push((id, node)),
+        }
+    }
+
+    for (id, node) in unclustered {
+        out.push_str(&format!("  {};\n", node_decl(id, node)));
+    }
+
+    for (cluster, members) in &clusters {
+        out.push_str(&format!("  subgraph {cluster} {{\n"));
+        for (id, node) in members {
+            out.push_str(&format!("    {};\n", node_decl(*id, node)));
+        }
+        out.push_str("  }\n");
+    }
+
+    for (out_pin, in_pin) in snarl.wires() {
+        let mut attr_list = String::new();
+        if let Some(attrs) = edge_attrs_for(edge_styles, out_pin, in_pin) {
+            for (key, value) in attrs {
+                if !attr_list.is_empty() {
+                    attr_list.push_str(", ");
+                }
+                attr_list.push_str(&format!("{key}=\"{}\"", escape_dot_string(value)));
+            }
+        }
+        if attr_list.is_empty() {
+            out.push_str(&format!(
+                "  {} -> {};\n",
+                dot_ident(out_pin.node),
+                dot_ident(in_pin.node)
+            ));
+        } else {
+            out.push_str(&format!(
+                "  {} -> {} [{attr_list}];\n",
+                dot_ident(out_pin.node),
+                dot_ident(in_pin.node)
+            ));
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// A DOT identifier derived from the `NodeId` rather than the node's (user-facing,
+/// non-unique, freely-charactered) name, so it's always a valid bareword and always
+/// unique, whatever the name contains.
+fn dot_ident(id: NodeId) -> String {
+    format!("n{}", id.0)
+}
+
+/// Escape the characters DOT requires inside a quoted string.
+fn escape_dot_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// A node statement body (`n3 [label="...", color="red"]`) shared by the
+/// top-level and clustered node-emission passes in `snarl_to_dot`.
+fn node_decl(id: NodeId, node: &Node) -> String {
+    let mut attr_list = format!("label=\"{}\"", escape_dot_string(node.name()));
+    for (key, value) in &node.attrs {
+        // "cluster" is bookkeeping this parser attaches, not a real DOT attribute;
+        // cluster membership round-trips as a `subgraph cluster_*` block instead.
+        if key == "cluster" {
+            continue;
+        }
+        attr_list.push_str(&format!(", {key}=\"{}\"", escape_dot_string(value)));
+    }
+    format!("{} [{attr_list}]", dot_ident(id))
+}
+
+/// Recursively flatten a statement list, descending into nested `Stmt::Subgraph`
+/// blocks (`digraph`/`graph` bodies may nest subgraphs arbitrarily deep) so
+/// callers see every `Stmt::Node`/`Stmt::Edge` regardless of how deeply it's
+/// nested. Each statement is paired with the name of its innermost enclosing
+/// `cluster_*` subgraph, if any, so cluster membership survives flattening.
+fn flatten_stmts<'a>(
+    list: &'a StmtList,
+    cluster: Option<&str>,
+    out: &mut Vec<(Option<String>, &'a Stmt)>,
+) {
+    for stmt in list.list.iter() {
+        if let Stmt::Subgraph(sub) = stmt {
+            let nested_cluster = sub
+                .id
+                .as_ref()
+                .filter(|id| id.starts_with("cluster"))
+                .cloned()
+                .or_else(|| cluster.map(str::to_string));
+            flatten_stmts(&sub.list, nested_cluster.as_deref(), out);
+        } else {
+            out.push((cluster.map(str::to_string), stmt));
+        }
+    }
+}
+
+/// Map each node name to the `cluster_*` subgraph it's declared (or first
+/// referenced) inside, so clustered DOT input keeps its grouping once flattened
+/// into the snarl graph. Nodes outside any `cluster_*` subgraph are absent.
+fn cluster_membership(graph: &Graph) -> BTreeMap<String, String> {
+    let mut stmts = Vec::new();
+    flatten_stmts(&graph.list, None, &mut stmts);
+
+    let mut clusters = BTreeMap::new();
+    for (cluster, stmt) in stmts {
+        let Some(cluster) = cluster else { continue };
+        match stmt {
+            Stmt::Node(NodeStmt { id, .. }) => {
+                clusters.entry(id.name.clone()).or_insert_with(|| cluster.clone());
+            }
+            Stmt::Edge(EdgeStmt { from, to, .. }) => {
+                clusters
+                    .entry(from.name.clone())
+                    .or_insert_with(|| cluster.clone());
+                for (to_id, ..) in to.iter() {
+                    clusters
+                        .entry(to_id.name.clone())
+                        .or_insert_with(|| cluster.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    clusters
+}
+
 fn node_id_from_label(
     g: &Graph,
     id_or_label: &str,
@@ -372,7 +962,10 @@ fn node_id_from_label(
         return Some(s.clone());
     }
 
-    for s in g.list.list.iter() {
+    let mut stmts = Vec::new();
+    flatten_stmts(&g.list, None, &mut stmts);
+
+    for (_, s) in stmts {
         let Stmt::Node(NodeStmt { id, list }) = s else {
             continue;
         };
@@ -397,10 +990,38 @@ fn node_id_from_label(
     None
 }
 
+/// Collect the attribute list (`color`, `style`, `shape`, ...) DOT attached to the
+/// `Stmt::Node` statement matching `id_or_label`, so parsed nodes keep more than
+/// just their label.
+fn node_attrs_from_label(g: &Graph, id_or_label: &str) -> AttrMap {
+    let mut attrs = AttrMap::new();
+    let mut stmts = Vec::new();
+    flatten_stmts(&g.list, None, &mut stmts);
+
+    for (_, s) in stmts {
+        let Stmt::Node(NodeStmt { id, list }) = s else {
+            continue;
+        };
+        let matches = id.name == id_or_label
+            || list
+                .list
+                .iter()
+                .any(|att| att.0 == "label" && att.1 == id_or_label);
+        if matches {
+            // `label` already lives in `Node::name`.
+            for att in list.list.iter().filter(|att| att.0 != "label") {
+                attrs.insert(att.0.clone(), att.1.clone());
+            }
+        }
+    }
+    attrs
+}
+
 /// Parse flow
 /// g: Graph = DotParser.new(&input).process();
-fn parse_dot(snarl: &mut Snarl<Node>, input: &str) -> Result<()> {
+fn parse_dot(snarl: &mut Snarl<Node>, input: &str) -> Result<EdgeStyles> {
     let mut node_map = BTreeMap::new();
+    let mut edge_styles = EdgeStyles::new();
     let mut parser = DotParser::new(&input);
 
     let graph = parser.process().map_err(Error::DotParserError)?;
@@ -411,6 +1032,8 @@ fn parse_dot(snarl: &mut Snarl<Node>, input: &str) -> Result<()> {
     lower_vg(&mut visual_graph);
     Placer::new(&mut visual_graph).layout(false);
 
+    let clusters = cluster_membership(&graph);
+
     // get all the positions and insert them as nodes
     for nh in visual_graph.iter_nodes() {
         // if not an edge
@@ -422,20 +1045,36 @@ fn parse_dot(snarl: &mut Snarl<Node>, input: &str) -> Result<()> {
             };
             // this is the "label" i need the node "id"
             let name = node_name(visual_graph.element(nh))?;
-            let node = Node::new(&name);
+            let mut attrs = node_attrs_from_label(&graph, &name);
+            if let Some(cluster) = clusters.get(&name) {
+                attrs.insert("cluster".to_string(), cluster.clone());
+            }
+            let node = Node::with_attrs(&name, attrs);
             let snarl_node_id = snarl.insert_node(pos, node);
             // save the snarl node_id by it's 'name' wich is dot's NodeId.name or label attr
             node_map.insert(name, snarl_node_id);
         }
     }
-    // get the edges (currently from DOT)
-    for g in graph.list.list.iter() {
-        let Stmt::Edge(EdgeStmt { from, to, .. }) = g else {
+    // get the edges, recursing into subgraphs so edges declared inside a
+    // `subgraph`/`cluster_*` block (and undirected `graph { A -- B }` edges,
+    // which parse to the same `EdgeStmt` shape) are connected too.
+    let mut stmts = Vec::new();
+    flatten_stmts(&graph.list, None, &mut stmts);
+    for (_, g) in stmts {
+        let Stmt::Edge(EdgeStmt { from, to, list, .. }) = g else {
             continue;
         };
 
+        // the edge's own `[color=..., style=...]` list, separate from either
+        // endpoint's node attrs, applies to every pin pair this statement connects
+        let mut attrs = AttrMap::new();
+        for att in list.list.iter() {
+            attrs.insert(att.0.clone(), att.1.clone());
+        }
+
         // given a dot id, cehck if it's in the node_map
-        let from_node_id = node_id_from_label(&graph, &from.name, &node_map).unwrap();
+        let from_node_id = node_id_from_label(&graph, &from.name, &node_map)
+            .ok_or_else(|| Error::UnknownNode(from.name.clone()))?;
         // start of edge
         let start = OutPinId {
             node: from_node_id.clone(),
@@ -444,15 +1083,74 @@ fn parse_dot(snarl: &mut Snarl<Node>, input: &str) -> Result<()> {
 
         // start can connect to multiple ends
         for (dot_id, ..) in to.iter() {
-            let Some(snarl_to_node_id) = node_id_from_label(&graph, &dot_id.name, &node_map) else {
-                panic!();
-            };
+            let snarl_to_node_id = node_id_from_label(&graph, &dot_id.name, &node_map)
+                .ok_or_else(|| Error::UnknownNode(dot_id.name.clone()))?;
             let stop = InPinId {
                 node: snarl_to_node_id.clone(),
                 input: 0,
             };
             snarl.connect(start, stop);
+            edge_styles.push((start, stop, attrs.clone()));
+        }
+    }
+    Ok(edge_styles)
+}
+
+/// Rebuild a `VisualGraph` from the *current* snarl contents (not a fresh parse)
+/// and re-run the placer, then write the resulting positions back onto the
+/// existing `NodeId`s without touching any node payload or connection.
+fn relayout(snarl: &mut Snarl<Node>) -> Result<()> {
+    let mut vg = VisualGraph::new(Orientation::TopToBottom);
+
+    // Bidirectional mapping so positions land back on the same snarl nodes
+    // instead of recreating them.
+    let mut snarl_to_layout: BTreeMap<NodeId, NodeHandle> = BTreeMap::new();
+    let mut layout_to_snarl: BTreeMap<NodeHandle, NodeId> = BTreeMap::new();
+
+    for (id, node) in snarl.node_ids() {
+        let shape = match node.attrs.get("shape").map(String::as_str) {
+            Some("doublecircle") => ShapeKind::DoubleCircle(node.name.clone()),
+            Some("circle") => ShapeKind::Circle(node.name.clone()),
+            _ => ShapeKind::Box(node.name.clone()),
+        };
+        let element = Element::create(
+            shape,
+            StyleAttr::simple(),
+            Orientation::TopToBottom,
+            Point::new(100.0, 50.0),
+        );
+        let handle = vg.add_node(element);
+        snarl_to_layout.insert(id, handle);
+        layout_to_snarl.insert(handle, id);
+    }
+
+    for (from, to) in snarl.wires() {
+        if let (Some(&f), Some(&t)) = (
+            snarl_to_layout.get(&from.node),
+            snarl_to_layout.get(&to.node),
+        ) {
+            vg.add_edge(Arrow::simple(""), f, t);
         }
     }
+
+    lower_vg(&mut vg);
+    Placer::new(&mut vg).layout(false);
+
+    for nh in vg.iter_nodes() {
+        if vg.is_connector(nh) {
+            continue;
+        }
+        let Some(&id) = layout_to_snarl.get(&nh) else {
+            continue;
+        };
+        let mid = vg.pos(nh).middle();
+        if let Some(info) = snarl.get_node_info_mut(id) {
+            info.pos = egui::Pos2 {
+                x: mid.x as f32,
+                y: mid.y as f32,
+            };
+        }
+    }
+
     Ok(())
 }